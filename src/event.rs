@@ -1,4 +1,4 @@
-use std::{any::Any, sync::Arc};
+use std::{any::Any, any::TypeId, collections::HashSet, sync::Arc};
 
 /// The type of time stamp.
 ///
@@ -18,13 +18,40 @@ pub struct UpdateArgs {
     pub dt: f64,
 }
 
+/// Render arguments.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RenderArgs {
+    /// Extrapolated time in seconds, for interpolated rendering between
+    /// updates.
+    pub ext_dt: f64,
+    /// The width and height of the rendered window, in points.
+    pub window_size: [f64; 2],
+    /// The width and height of the rendered window, in pixels.
+    pub draw_size: [u32; 2],
+}
+
+/// After-render arguments, sent right after rendering finishes.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, serde::Deserialize, serde::Serialize)]
+pub struct AfterRenderArgs;
+
+/// Idle arguments, such as the amount of idle time available.
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, serde::Deserialize, serde::Serialize)]
+pub struct IdleArgs {
+    /// Seconds of idle time available.
+    pub dt: f64,
+}
+
 /// Models loop events.
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize)]
 pub enum Loop {
+    /// Render the state of the application.
+    Render(RenderArgs),
+    /// Called right after rendering, before swapping buffers.
+    AfterRender(AfterRenderArgs),
     /// Update the state of the application.
     Update(UpdateArgs),
-    // Do background tasks that can be done incrementally.
-    // Idle(IdleArgs),
+    /// Do background tasks that can be done incrementally.
+    Idle(IdleArgs),
 }
 
 impl From<UpdateArgs> for Event {
@@ -33,6 +60,24 @@ impl From<UpdateArgs> for Event {
     }
 }
 
+impl From<RenderArgs> for Event {
+    fn from(args: RenderArgs) -> Self {
+        Event::Loop(Loop::Render(args))
+    }
+}
+
+impl From<AfterRenderArgs> for Event {
+    fn from(args: AfterRenderArgs) -> Self {
+        Event::Loop(Loop::AfterRender(args))
+    }
+}
+
+impl From<IdleArgs> for Event {
+    fn from(args: IdleArgs) -> Self {
+        Event::Loop(Loop::Idle(args))
+    }
+}
+
 /// Models all events.
 #[derive(Clone)]
 pub enum Event {
@@ -86,6 +131,424 @@ impl UpdateEvent for Event {
     }
 }
 
+/// When the next frame should be rendered.
+pub trait RenderEvent: Sized {
+    /// Creates a render event.
+    fn from_render_args(args: &RenderArgs, old_event: &Self) -> Option<Self>;
+    /// Calls closure if this is a render event.
+    fn render<U, F>(&self, f: F) -> Option<U>
+    where
+        F: FnMut(&RenderArgs) -> U;
+    /// Returns render arguments.
+    fn render_args(&self) -> Option<RenderArgs> {
+        self.render(|args| *args)
+    }
+}
+
+impl RenderEvent for Event {
+    fn from_render_args(args: &RenderArgs, _old_event: &Self) -> Option<Self> {
+        Some(Event::Loop(Loop::Render(*args)))
+    }
+
+    fn render<U, F>(&self, mut f: F) -> Option<U>
+    where
+        F: FnMut(&RenderArgs) -> U,
+    {
+        match *self {
+            Event::Loop(Loop::Render(ref args)) => Some(f(args)),
+            _ => None,
+        }
+    }
+}
+
+/// When the next frame has finished rendering.
+pub trait AfterRenderEvent: Sized {
+    /// Creates an after-render event.
+    fn from_after_render_args(args: &AfterRenderArgs, old_event: &Self) -> Option<Self>;
+    /// Calls closure if this is an after-render event.
+    fn after_render<U, F>(&self, f: F) -> Option<U>
+    where
+        F: FnMut(&AfterRenderArgs) -> U;
+    /// Returns after-render arguments.
+    fn after_render_args(&self) -> Option<AfterRenderArgs> {
+        self.after_render(|args| *args)
+    }
+}
+
+impl AfterRenderEvent for Event {
+    fn from_after_render_args(args: &AfterRenderArgs, _old_event: &Self) -> Option<Self> {
+        Some(Event::Loop(Loop::AfterRender(*args)))
+    }
+
+    fn after_render<U, F>(&self, mut f: F) -> Option<U>
+    where
+        F: FnMut(&AfterRenderArgs) -> U,
+    {
+        match *self {
+            Event::Loop(Loop::AfterRender(ref args)) => Some(f(args)),
+            _ => None,
+        }
+    }
+}
+
+/// When there is idle time available to do background tasks.
+pub trait IdleEvent: Sized {
+    /// Creates an idle event.
+    fn from_idle_args(args: &IdleArgs, old_event: &Self) -> Option<Self>;
+    /// Creates an idle event with the available idle time.
+    fn from_dt(dt: f64, old_event: &Self) -> Option<Self> {
+        IdleEvent::from_idle_args(&IdleArgs { dt }, old_event)
+    }
+    /// Calls closure if this is an idle event.
+    fn idle<U, F>(&self, f: F) -> Option<U>
+    where
+        F: FnMut(&IdleArgs) -> U;
+    /// Returns idle arguments.
+    fn idle_args(&self) -> Option<IdleArgs> {
+        self.idle(|args| *args)
+    }
+}
+
+impl IdleEvent for Event {
+    fn from_idle_args(args: &IdleArgs, _old_event: &Self) -> Option<Self> {
+        Some(Event::Loop(Loop::Idle(*args)))
+    }
+
+    fn idle<U, F>(&self, mut f: F) -> Option<U>
+    where
+        F: FnMut(&IdleArgs) -> U,
+    {
+        match *self {
+            Event::Loop(Loop::Idle(ref args)) => Some(f(args)),
+            _ => None,
+        }
+    }
+}
+
+/// The well-known `EventId` used to identify update events carried by a
+/// `GenericEvent`.
+pub const UPDATE_EVENT_ID: EventId = EventId("bonsai/update");
+
+/// Implemented by a custom event enum to get `UpdateEvent` for free,
+/// instead of being locked into this crate's `Event` type.
+///
+/// `RenderEvent`, `AfterRenderEvent` and `IdleEvent` are implemented for
+/// `Event` only; implementing `GenericEvent` does not provide them.
+pub trait GenericEvent: Sized {
+    /// Returns the id of the event.
+    fn event_id(&self) -> EventId;
+    /// Calls the closure with the event's arguments, if this event
+    /// carries any under `id`.
+    fn with_args<U>(&self, id: EventId, f: &mut dyn FnMut(&dyn Any) -> U) -> U;
+    /// Creates a new event from an id and type-erased arguments, falling
+    /// back to `old_event` for anything the event doesn't replace.
+    fn from_args(id: EventId, any: Arc<dyn Any + Send + Sync>, old_event: &Self) -> Option<Self>;
+}
+
+impl<T: GenericEvent> UpdateEvent for T {
+    fn from_update_args(args: &UpdateArgs, old_event: &Self) -> Option<Self> {
+        GenericEvent::from_args(UPDATE_EVENT_ID, Arc::new(*args), old_event)
+    }
+
+    fn update<U, F>(&self, mut f: F) -> Option<U>
+    where
+        F: FnMut(&UpdateArgs) -> U,
+    {
+        if self.event_id() != UPDATE_EVENT_ID {
+            return None;
+        }
+        Some(self.with_args(UPDATE_EVENT_ID, &mut |any| {
+            f(any.downcast_ref::<UpdateArgs>().expect("Expected `UpdateArgs`"))
+        }))
+    }
+}
+
+/// A monotonically increasing identifier assigned to each event sent
+/// through an `Events` queue, in the order it was sent.
+pub type EventCount = u64;
+
+/// A single event together with the sequence number it was sent with.
+#[derive(Clone)]
+pub struct EventInstance<E> {
+    /// The order in which this event was sent, relative to all other
+    /// events sent through the same queue.
+    pub id: EventCount,
+    /// The event itself.
+    pub event: E,
+}
+
+/// Tracks how much of an `Events<E>` queue a reader has already
+/// consumed.
+///
+/// Create one per reader with `EventReader::new` and pass it to
+/// `Events::read`. The cursor always starts at the oldest retained
+/// event, so a reader created after some events were sent still
+/// observes them.
+pub struct EventReader<E> {
+    last_seen: EventCount,
+    _marker: ::std::marker::PhantomData<E>,
+}
+
+impl<E> Default for EventReader<E> {
+    fn default() -> Self {
+        EventReader {
+            last_seen: 0,
+            _marker: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<E> EventReader<E> {
+    /// Creates a reader that has not yet seen any events.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// A double-buffered queue of events of type `E`.
+///
+/// Events are pushed into whichever of the two internal buffers is
+/// currently active. Calling `update` swaps the active buffer and
+/// clears the one that is now stale, so an event is kept through the
+/// first call to `update` after it is sent and dropped by the second.
+/// This guarantees that a
+/// reader sees every event at least once, no matter when it polls
+/// relative to `update`.
+pub struct Events<E> {
+    events_a: Vec<EventInstance<E>>,
+    events_b: Vec<EventInstance<E>>,
+    a_is_current: bool,
+    event_count: EventCount,
+}
+
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Events {
+            events_a: Vec::new(),
+            events_b: Vec::new(),
+            a_is_current: true,
+            event_count: 0,
+        }
+    }
+}
+
+impl<E> Events<E> {
+    /// Creates an empty event queue.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sends an event into the currently active buffer.
+    pub fn send(&mut self, event: E) {
+        // Ids start at 1 so a fresh `EventReader`, whose cursor starts at
+        // 0, does not miss the very first event ever sent.
+        self.event_count += 1;
+        let instance = EventInstance {
+            id: self.event_count,
+            event,
+        };
+        if self.a_is_current {
+            self.events_a.push(instance);
+        } else {
+            self.events_b.push(instance);
+        }
+    }
+
+    /// Swaps the active buffer and clears the buffer that is now stale.
+    ///
+    /// Call this once per frame so that events sent before the previous
+    /// call still have one full cycle for readers to observe them.
+    pub fn update(&mut self) {
+        if self.a_is_current {
+            self.events_b.clear();
+        } else {
+            self.events_a.clear();
+        }
+        self.a_is_current = !self.a_is_current;
+    }
+
+    /// Returns the number of events currently retained in both buffers.
+    pub fn len(&self) -> usize {
+        self.events_a.len() + self.events_b.len()
+    }
+
+    /// Returns `true` if no events are currently retained.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes and returns every retained event, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = E> + '_ {
+        let (stale, current) = if self.a_is_current {
+            (&mut self.events_b, &mut self.events_a)
+        } else {
+            (&mut self.events_a, &mut self.events_b)
+        };
+        stale
+            .drain(..)
+            .chain(current.drain(..))
+            .map(|instance| instance.event)
+    }
+
+    /// Returns an iterator over only the events sent since the last
+    /// `update`, ignoring anything retained purely for stragglers.
+    pub fn iter_current_update_events(&self) -> impl Iterator<Item = &E> {
+        let current = if self.a_is_current {
+            &self.events_a
+        } else {
+            &self.events_b
+        };
+        current.iter().map(|instance| &instance.event)
+    }
+
+    /// Returns an iterator, in send order, over every event with an id
+    /// greater than the reader's cursor, then advances the cursor so
+    /// those events are not yielded again.
+    pub fn read<'a>(&'a self, reader: &mut EventReader<E>) -> impl Iterator<Item = &'a E> {
+        let (stale, current) = if self.a_is_current {
+            (&self.events_b, &self.events_a)
+        } else {
+            (&self.events_a, &self.events_b)
+        };
+        let last_seen = reader.last_seen;
+        let max_id = stale
+            .iter()
+            .chain(current.iter())
+            .map(|instance| instance.id)
+            .max()
+            .unwrap_or(0);
+        reader.last_seen = reader.last_seen.max(max_id);
+        stale
+            .iter()
+            .chain(current.iter())
+            .filter(move |instance| instance.id > last_seen)
+            .map(|instance| &instance.event)
+    }
+}
+
+/// Lets an `EventRegistry` drive an `Events<E>` queue's `update` without
+/// knowing its concrete type.
+trait ErasedEvents {
+    fn update(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<E: 'static> ErasedEvents for Events<E> {
+    fn update(&mut self) {
+        Events::update(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Controls when a registry is allowed to age its events via `update`.
+///
+/// Draining every frame drops events too fast for a consumer that only
+/// runs on a fixed timestep, but waiting for a fixed-step tick that
+/// never arrives leaks events forever. An app with no fixed-timestep
+/// schedule must stay in `Always` so events are never silently
+/// accumulated.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ShouldUpdateEvents {
+    /// Update unconditionally, every frame.
+    #[default]
+    Always,
+    /// A fixed-timestep tick is expected but has not arrived this
+    /// frame, so the update is skipped.
+    Waiting,
+    /// A fixed-timestep tick arrived; update now, then fall back to
+    /// `Waiting` until the next tick.
+    Ready,
+}
+
+/// Drives many `Events<E>` queues from one exclusive update pass.
+///
+/// Without a registry, an app that tracks several event types has to
+/// schedule a separate update for each one. `EventRegistry` collapses
+/// that into a single linear sweep: the per-type work is just a buffer
+/// swap, so dispatch overhead is what dominates at scale.
+#[derive(Default)]
+pub struct EventRegistry {
+    registered: HashSet<TypeId>,
+    queues: Vec<Box<dyn ErasedEvents>>,
+    mode: ShouldUpdateEvents,
+}
+
+impl EventRegistry {
+    /// Creates an empty registry. Starts in `ShouldUpdateEvents::Always`
+    /// so events are never retained forever by default.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the current update mode.
+    pub fn mode(&self) -> ShouldUpdateEvents {
+        self.mode
+    }
+
+    /// Sets the update mode.
+    pub fn set_mode(&mut self, mode: ShouldUpdateEvents) {
+        self.mode = mode;
+    }
+
+    /// Signals that a fixed-timestep tick has arrived, so the next call
+    /// to `update_all` runs (and only that one). Has no effect in
+    /// `Always` mode.
+    pub fn signal_ready(&mut self) {
+        if self.mode != ShouldUpdateEvents::Always {
+            self.mode = ShouldUpdateEvents::Ready;
+        }
+    }
+
+    /// Registers an `Events<E>` queue, taking ownership of it.
+    ///
+    /// Idempotent: calling this more than once for the same `E` has no
+    /// effect after the first call.
+    pub fn register<E: 'static>(&mut self, events: Events<E>) {
+        if !self.registered.insert(TypeId::of::<E>()) {
+            return;
+        }
+        self.queues.push(Box::new(events));
+    }
+
+    /// Calls `update` on every registered queue, according to `mode`:
+    /// unconditionally in `Always` mode, skipped in `Waiting` mode, and
+    /// once in `Ready` mode before falling back to `Waiting`.
+    pub fn update_all(&mut self) {
+        if self.mode == ShouldUpdateEvents::Waiting {
+            return;
+        }
+        for queue in &mut self.queues {
+            queue.update();
+        }
+        if self.mode == ShouldUpdateEvents::Ready {
+            self.mode = ShouldUpdateEvents::Waiting;
+        }
+    }
+
+    /// Returns a reference to the queue registered for `E`, if any.
+    pub fn get<E: 'static>(&self) -> Option<&Events<E>> {
+        self.queues
+            .iter()
+            .find_map(|queue| queue.as_any().downcast_ref::<Events<E>>())
+    }
+
+    /// Returns a mutable reference to the queue registered for `E`, if
+    /// any.
+    pub fn get_mut<E: 'static>(&mut self) -> Option<&mut Events<E>> {
+        self.queues
+            .iter_mut()
+            .find_map(|queue| queue.as_any_mut().downcast_mut::<Events<E>>())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,4 +561,78 @@ mod tests {
         let e: Event = UpdateArgs { dt: 0.0 }.into();
         let _: Option<Event> = UpdateEvent::from_update_args(&UpdateArgs { dt: 1.0 }, &e);
     }
+
+    #[test]
+    fn test_events_read_then_dropped_on_second_update() {
+        let mut events: Events<u32> = Events::new();
+        let mut reader = EventReader::new();
+
+        events.send(1);
+        events.send(2);
+        assert_eq!(events.read(&mut reader).cloned().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(events.read(&mut reader).count(), 0);
+
+        events.update();
+        assert_eq!(events.len(), 2);
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_events_drain_and_current_only() {
+        let mut events: Events<u32> = Events::new();
+        events.send(1);
+        events.update();
+        events.send(2);
+
+        assert_eq!(
+            events.iter_current_update_events().cloned().collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(events.drain().collect::<Vec<_>>(), vec![1, 2]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_event_registry_update_all() {
+        let mut registry = EventRegistry::new();
+        registry.register::<u32>(Events::new());
+        registry.register::<&'static str>(Events::new());
+        registry.register::<u32>(Events::new());
+
+        registry.get_mut::<u32>().unwrap().send(1);
+        registry.get_mut::<&'static str>().unwrap().send("a");
+
+        registry.update_all();
+        assert_eq!(registry.get::<u32>().unwrap().len(), 1);
+        registry.update_all();
+        assert!(registry.get::<u32>().unwrap().is_empty());
+        assert!(registry.get::<&'static str>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_event_registry_waits_for_fixed_timestep() {
+        let mut registry = EventRegistry::new();
+        assert_eq!(registry.mode(), ShouldUpdateEvents::Always);
+
+        registry.register::<u32>(Events::new());
+        registry.set_mode(ShouldUpdateEvents::Waiting);
+        registry.get_mut::<u32>().unwrap().send(1);
+
+        registry.update_all();
+        assert_eq!(registry.get::<u32>().unwrap().len(), 1);
+
+        registry.signal_ready();
+        registry.update_all();
+        assert_eq!(registry.mode(), ShouldUpdateEvents::Waiting);
+        assert_eq!(registry.get::<u32>().unwrap().len(), 1);
+
+        // Still waiting: the event must not be dropped while no tick arrives.
+        registry.update_all();
+        assert_eq!(registry.get::<u32>().unwrap().len(), 1);
+
+        registry.signal_ready();
+        registry.update_all();
+        assert!(registry.get::<u32>().unwrap().is_empty());
+    }
 }